@@ -0,0 +1,4 @@
+mod ghcr;
+mod oci;
+
+pub use crate::ghcr::{Ghcr, ImageMeta, PlatformArtifact, DEFAULT_MAX_LAYER_SIZE};
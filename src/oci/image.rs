@@ -3,43 +3,78 @@ use std::fs;
 use std::path::Path;
 
 use anyhow::Result;
+use oci_spec::image::{
+    Arch, Descriptor, DescriptorBuilder, ImageConfiguration, ImageConfigurationBuilder,
+    ImageIndex, ImageIndexBuilder, ImageManifest, MediaType, Os, Platform, PlatformBuilder,
+    RootFsBuilder,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use serde_json::{json, Value};
 
-use crate::oci::schema::{
-    Schema, IMAGE_CONFIG_SCHEMA_URI, IMAGE_INDEX_SCHEMA_URI, IMAGE_LAYOUT_SCHEMA_URI,
-    IMAGE_MANIFEST_SCHEMA_URI,
-};
-
-pub(crate) struct Image {
-    schema: Schema,
-}
+pub(crate) struct Image;
 
 impl Image {
-    pub(crate) fn new() -> Result<Self> {
-        let mut schema = Schema::new();
-        schema.load_schemas()?;
-        Ok(Self { schema })
+    pub(crate) fn new() -> Self {
+        Self
+    }
+
+    /// Deserializes a bare string (e.g. "amd64") into one of `oci-spec`'s
+    /// wire-format enums, without requiring a dedicated `FromStr` impl.
+    fn parse_enum<T: DeserializeOwned>(value: &str) -> Result<T> {
+        Ok(serde_json::from_value(Value::String(value.to_string()))?)
+    }
+
+    /// Parses a media type string, falling back to `MediaType::Other` for
+    /// one that isn't among `oci-spec`'s well-known variants. Media types
+    /// are open-ended by the image spec, so arbitrary artifact/layer types
+    /// must round-trip as-is.
+    pub(crate) fn media_type(value: &str) -> MediaType {
+        Self::parse_enum(value).unwrap_or_else(|_| MediaType::Other(value.to_string()))
+    }
+
+    /// Builds the `platform` descriptor field shared by every manifest entry
+    /// listed in an image index.
+    pub(crate) fn platform(arch: &str, os: &str, variant: Option<&str>) -> Result<Platform> {
+        let mut builder = PlatformBuilder::default();
+        builder
+            .architecture(Self::parse_enum::<Arch>(arch)?)
+            .os(Self::parse_enum::<Os>(os)?);
+        if let Some(variant) = variant {
+            builder.variant(variant.to_string());
+        }
+        Ok(builder.build()?)
     }
 
-    fn write_hash(
+    fn write_json<T: Serialize>(
         directory: &Path,
-        hash: &Value,
+        value: &T,
         filename: Option<String>,
-    ) -> Result<(String, usize)> {
-        let json = serde_json::to_string_pretty(&hash)?;
+    ) -> Result<(String, String)> {
+        let json = serde_json::to_string_pretty(value)?;
         let json_sha256 = sha256::digest(json.clone());
         let filename = filename.unwrap_or(json_sha256.clone());
-        let path = directory.join(filename);
-        fs::write(path, json.clone())?;
+        fs::write(directory.join(filename), &json)?;
+        Ok((json_sha256, json))
+    }
 
-        Ok((json_sha256, json.len()))
+    fn write_hash<T: Serialize>(
+        directory: &Path,
+        value: &T,
+        media_type: MediaType,
+        filename: Option<String>,
+    ) -> Result<Descriptor> {
+        let (json_sha256, json) = Self::write_json(directory, value, filename)?;
+        Ok(DescriptorBuilder::default()
+            .media_type(media_type)
+            .digest(format!("sha256:{json_sha256}"))
+            .size(json.len() as i64)
+            .build()?)
     }
 
     pub(crate) fn write_image_layout(&self, root: &Path) -> Result<()> {
         let image_layout = json!({ "imageLayoutVersion": "1.0.0" });
-        self.schema
-            .validate_schema(IMAGE_LAYOUT_SCHEMA_URI, &image_layout)?;
-        Self::write_hash(root, &image_layout, Some("oci-layout".to_string()))?;
+        Self::write_json(root, &image_layout, Some("oci-layout".to_string()))?;
         Ok(())
     }
 
@@ -61,69 +96,65 @@ impl Image {
         &self,
         arch: &str,
         os: &str,
-        tar_sha256: &str,
+        diff_ids: &[String],
         blobs: &Path,
-    ) -> Result<(String, usize)> {
-        let image_config = json!({
-            "architecture": arch,
-            "os": os,
-            "rootfs": {
-                "type": "layers",
-                "diff_ids": [
-                    format!("sha256:{tar_sha256}")
-                ]
-            }
-        });
-        self.schema
-            .validate_schema(IMAGE_CONFIG_SCHEMA_URI, &image_config)?;
-        Self::write_hash(blobs, &image_config, None)
+    ) -> Result<Descriptor> {
+        let rootfs = RootFsBuilder::default()
+            .typ("layers")
+            .diff_ids(diff_ids.to_vec())
+            .build()?;
+        let image_config: ImageConfiguration = ImageConfigurationBuilder::default()
+            .architecture(Self::parse_enum::<Arch>(arch)?)
+            .os(Self::parse_enum::<Os>(os)?)
+            .rootfs(rootfs)
+            .build()?;
+        Self::write_hash(blobs, &image_config, MediaType::ImageConfig, None)
+    }
+
+    /// Writes a caller-supplied config blob (e.g. `{}` for an artifact with
+    /// no meaningful config) instead of a full image configuration.
+    pub(crate) fn write_artifact_config(
+        &self,
+        config: &Value,
+        media_type: MediaType,
+        blobs: &Path,
+    ) -> Result<Descriptor> {
+        Self::write_hash(blobs, config, media_type, None)
     }
 
     pub(crate) fn write_image_manifest(
         &self,
-        image_manifest: &Value,
+        image_manifest: &ImageManifest,
         blobs: &Path,
-    ) -> Result<(String, usize)> {
-        self.schema
-            .validate_schema(IMAGE_MANIFEST_SCHEMA_URI, image_manifest)?;
-        Self::write_hash(blobs, image_manifest, None)
+    ) -> Result<Descriptor> {
+        Self::write_hash(blobs, image_manifest, MediaType::ImageManifest, None)
     }
 
     pub(crate) fn write_image_index(
         &self,
-        manifests: &Vec<Value>,
-        annotations: &HashMap<String, String>,
+        image_index: &ImageIndex,
         blobs: &Path,
-    ) -> Result<(String, usize)> {
-        let image_index = json!({
-            "schemaVersion": 2,
-            "manifests": manifests,
-            "annotations": annotations,
-        });
-        self.schema
-            .validate_schema(IMAGE_INDEX_SCHEMA_URI, &image_index)?;
-        Self::write_hash(blobs, &image_index, None)
+    ) -> Result<Descriptor> {
+        Self::write_hash(blobs, image_index, MediaType::ImageIndex, None)
     }
 
     pub(crate) fn write_index_json(
         &self,
-        index_json_sha256: &str,
-        index_json_size: usize,
+        index_descriptor: &Descriptor,
         root: &Path,
         annotations: &HashMap<String, String>,
     ) -> Result<()> {
-        let index_json = json!({
-            "schemaVersion": 2,
-            "manifests": [{
-                "mediaType": "application/vnd.oci.image.index.v1+json",
-                "digest": format!("sha256:{index_json_sha256}"),
-                "size": index_json_size,
-                "annotations": annotations,
-            }],
-        });
-        self.schema
-            .validate_schema(IMAGE_INDEX_SCHEMA_URI, &index_json)?;
-        Self::write_hash(root, &index_json, Some("index.json".to_string()))?;
+        let descriptor = DescriptorBuilder::default()
+            .media_type(index_descriptor.media_type().clone())
+            .digest(index_descriptor.digest().clone())
+            .size(*index_descriptor.size())
+            .annotations(annotations.clone())
+            .build()?;
+        let index_json: ImageIndex = ImageIndexBuilder::default()
+            .schema_version(2u32)
+            .manifests(vec![descriptor])
+            .build()?;
+        Self::write_json(root, &index_json, Some("index.json".to_string()))?;
         Ok(())
     }
 }
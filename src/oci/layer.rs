@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use flate2::bufread::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use tar::{Archive, Builder};
+
+struct Entry {
+    path: PathBuf,
+    size: u64,
+}
+
+/// The first path component of an entry, used as its bin-packing group key
+/// so that related files (e.g. everything under the same top-level
+/// directory) co-locate in the same layer and rarely-changing files end up
+/// in stable, dedup-friendly layers across versions.
+fn group_key(path: &Path) -> PathBuf {
+    match path.components().next() {
+        Some(component) => Path::new(component.as_os_str()).to_path_buf(),
+        None => path.to_path_buf(),
+    }
+}
+
+fn list_entries(tar_gz: &Path) -> Result<Vec<Entry>> {
+    let decoder = GzDecoder::new(BufReader::new(File::open(tar_gz)?));
+    let mut archive = Archive::new(decoder);
+
+    let mut entries = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        entries.push(Entry {
+            path: entry.path()?.to_path_buf(),
+            size: entry.header().size()?,
+        });
+    }
+    Ok(entries)
+}
+
+/// Greedily bins entries into layers capped at `max_layer_size` uncompressed
+/// bytes. Entries sharing a `group_key` are never split across bins, so a
+/// group larger than the cap gets a bin of its own that exceeds it.
+fn bin_pack(entries: &[Entry], max_layer_size: u64) -> Vec<Vec<PathBuf>> {
+    let mut groups: Vec<(PathBuf, Vec<&Entry>)> = Vec::new();
+    for entry in entries {
+        let key = group_key(&entry.path);
+        match groups.iter_mut().find(|(group_key, _)| *group_key == key) {
+            Some((_, group)) => group.push(entry),
+            None => groups.push((key, vec![entry])),
+        }
+    }
+
+    let mut bins: Vec<Vec<PathBuf>> = vec![Vec::new()];
+    let mut bin_size = 0u64;
+    for (_, group) in groups {
+        let group_size: u64 = group.iter().map(|entry| entry.size).sum();
+        if bin_size > 0 && bin_size + group_size > max_layer_size {
+            bins.push(Vec::new());
+            bin_size = 0;
+        }
+        bin_size += group_size;
+        bins.last_mut()
+            .unwrap()
+            .extend(group.into_iter().map(|entry| entry.path.clone()));
+    }
+    bins
+}
+
+/// Re-reads `tar_gz` and re-emits each bin as its own valid tar+gzip file in
+/// `dir`, preserving every entry's original tar header.
+fn repack(tar_gz: &Path, dir: &Path, bins: &[Vec<PathBuf>]) -> Result<Vec<PathBuf>> {
+    let bin_of: HashMap<&Path, usize> = bins
+        .iter()
+        .enumerate()
+        .flat_map(|(i, paths)| paths.iter().map(move |path| (path.as_path(), i)))
+        .collect();
+
+    let paths: Vec<PathBuf> = (0..bins.len())
+        .map(|i| dir.join(format!("layer-{i}.tar.gz")))
+        .collect();
+    let mut builders: Vec<Builder<GzEncoder<File>>> = paths
+        .iter()
+        .map(|path| -> Result<_> {
+            let file = File::create(path)?;
+            Ok(Builder::new(GzEncoder::new(file, Compression::default())))
+        })
+        .collect::<Result<_>>()?;
+
+    let decoder = GzDecoder::new(BufReader::new(File::open(tar_gz)?));
+    let mut archive = Archive::new(decoder);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_path_buf();
+        let bin = *bin_of.get(path.as_path()).unwrap_or(&0);
+
+        let header = entry.header().clone();
+        let mut data = Vec::with_capacity(header.size()? as usize);
+        entry.read_to_end(&mut data)?;
+        builders[bin].append(&header, data.as_slice())?;
+    }
+
+    for builder in builders {
+        builder.into_inner()?.finish()?;
+    }
+    Ok(paths)
+}
+
+/// Splits the tar entries of `tar_gz` into several tar+gzip files, each
+/// capped at `max_layer_size` uncompressed bytes (except where a single
+/// group is larger than the cap, see [`bin_pack`]), following the approach
+/// ostree-rs-ext uses for splitting a commit across OCI layers. Unchanged
+/// bins across versions re-emit byte-for-byte, so their compressed digest
+/// stays stable and the blob is skipped on push.
+pub(crate) fn split_into_layers(
+    tar_gz: &Path,
+    dir: &Path,
+    max_layer_size: u64,
+) -> Result<Vec<PathBuf>> {
+    let entries = list_entries(tar_gz)?;
+    let bins = bin_pack(&entries, max_layer_size);
+    repack(tar_gz, dir, &bins)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, size: u64) -> Entry {
+        Entry {
+            path: PathBuf::from(path),
+            size,
+        }
+    }
+
+    #[test]
+    fn group_key_is_first_path_component() {
+        assert_eq!(group_key(Path::new("usr/bin/ls")), PathBuf::from("usr"));
+        assert_eq!(
+            group_key(Path::new("Cargo.toml")),
+            PathBuf::from("Cargo.toml")
+        );
+    }
+
+    #[test]
+    fn bin_pack_splits_once_a_bin_would_exceed_the_cap() {
+        let entries = vec![entry("a/1", 40), entry("b/1", 40), entry("c/1", 40)];
+        let bins = bin_pack(&entries, 50);
+        assert_eq!(
+            bins,
+            vec![
+                vec![PathBuf::from("a/1")],
+                vec![PathBuf::from("b/1")],
+                vec![PathBuf::from("c/1")],
+            ]
+        );
+    }
+
+    #[test]
+    fn bin_pack_never_splits_a_group_across_bins() {
+        let entries = vec![entry("dir/1", 30), entry("dir/2", 30), entry("other/1", 10)];
+        let bins = bin_pack(&entries, 50);
+        assert_eq!(
+            bins,
+            vec![
+                vec![PathBuf::from("dir/1"), PathBuf::from("dir/2")],
+                vec![PathBuf::from("other/1")],
+            ]
+        );
+    }
+}
@@ -0,0 +1,5 @@
+mod image;
+mod layer;
+
+pub(crate) use image::Image;
+pub(crate) use layer::split_into_layers;